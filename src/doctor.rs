@@ -0,0 +1,77 @@
+//! Validates that the active theme/font configuration is actually usable,
+//! the way rustdoc's theme-checker flags a broken `--theme` argument before
+//! rendering starts.
+
+use std::collections::HashSet;
+
+use crate::{get_current_fonts, get_current_theme, Result, VsCodeSettings};
+
+/// CSS generic font family keywords: not an actual installed face, so these
+/// should never be flagged as unresolved
+const CSS_GENERIC_FAMILIES: &[&str] = &[
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+    "math",
+    "emoji",
+    "fangsong",
+];
+
+/// The result of [`check`]
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Whether `workbench.colorTheme` resolves to an installed extension
+    pub theme_ok: bool,
+    /// The configured theme name, if it did not resolve to anything installed
+    pub missing_theme: Option<String>,
+    /// Font families from `editor.fontFamily`/`terminal.integrated.fontFamily`
+    /// that aren't installed on the system
+    pub unresolved_fonts: Vec<String>,
+}
+
+/// Cross-references the active `workbench.colorTheme` and font settings
+/// against what's actually installed, surfacing dangling references left
+/// behind by an uninstalled theme or a typo'd font name
+pub fn check() -> Result<Report> {
+    let settings = VsCodeSettings::new()?;
+    let current_theme = get_current_theme()?;
+    let theme_ok = settings.find_theme(&current_theme).is_some();
+
+    let fonts = get_current_fonts()?;
+    let installed = installed_font_families();
+    let mut seen = HashSet::new();
+    let unresolved_fonts = fonts
+        .editor_fonts()
+        .into_iter()
+        .chain(fonts.terminal_fonts())
+        .filter(|family| !CSS_GENERIC_FAMILIES.contains(&family.to_lowercase().as_str()))
+        .filter(|family| !installed.contains(&family.to_lowercase()))
+        .filter(|family| seen.insert(family.clone()))
+        .collect();
+
+    Ok(Report {
+        theme_ok,
+        missing_theme: (!theme_ok).then_some(current_theme),
+        unresolved_fonts,
+    })
+}
+
+fn installed_font_families() -> HashSet<String> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    db.faces()
+        .flat_map(|face| {
+            face.families
+                .iter()
+                .map(|(name, _)| name.to_lowercase())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
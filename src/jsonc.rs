@@ -0,0 +1,167 @@
+//! Minimal JSONC support shared by theme file and settings.json parsing:
+//! stripping comments/trailing commas and looking up dotted paths.
+
+/// Strips `//` and `/* */` comments and trailing commas from a JSONC
+/// document, tolerating the sloppier dialect vscode ships its own config
+/// and theme files in
+pub(crate) fn strip(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => {
+                in_string = true;
+                out.push(ch);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            _ => out.push(ch),
+        }
+    }
+
+    strip_trailing_commas(&out)
+}
+
+fn strip_trailing_commas(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        if ch == '"' {
+            in_string = true;
+            out.push(ch);
+            continue;
+        }
+
+        if ch == ',' {
+            let next_significant = chars.clone().find(|c| !c.is_whitespace());
+            if matches!(next_significant, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+
+        out.push(ch);
+    }
+
+    out
+}
+
+/// Parses a JSONC document into a `serde_json::Value`
+pub(crate) fn parse(data: &str) -> crate::Result<serde_json::Value> {
+    Ok(serde_json::from_str(&strip(data))?)
+}
+
+/// Looks up a key in a parsed document. vscode stores settings as flat
+/// (dotted) string keys at every level it nests through — e.g.
+/// `workbench.colorCustomizations` is itself a flat top-level key whose
+/// object value has flat keys like `editor.background` — rather than as
+/// truly nested objects. The full key is tried verbatim first; failing
+/// that, each top-level prefix (longest first) is tried, descending into
+/// its object value and looking up the remainder as a single flat key.
+pub(crate) fn get<'a>(document: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    if let Some(value) = document.get(key) {
+        return Some(value);
+    }
+
+    let parts: Vec<&str> = key.split('.').collect();
+    for split in (1..parts.len()).rev() {
+        let prefix = parts[..split].join(".");
+        let remainder = parts[split..].join(".");
+        if let Some(value) = document.get(&prefix).and_then(|parent| parent.get(&remainder)) {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = r##"{
+        // a comment above the theme
+        "workbench.colorTheme": "Dracula",
+        "editor.fontFamily": "'Fira Code', Consolas, monospace",
+        /* block comment */
+        "workbench.colorCustomizations": {
+            "editor.background": "#000000",
+        },
+    }"##;
+
+    #[test]
+    fn strips_comments_and_trailing_commas() {
+        let stripped = strip(SETTINGS);
+        assert!(serde_json::from_str::<serde_json::Value>(&stripped).is_ok());
+    }
+
+    #[test]
+    fn gets_flat_dotted_key_verbatim() {
+        let document = parse(SETTINGS).unwrap();
+        assert_eq!(
+            get(&document, "workbench.colorTheme").and_then(|v| v.as_str()),
+            Some("Dracula")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_nested_path_for_nested_objects() {
+        let document = parse(SETTINGS).unwrap();
+        assert_eq!(
+            get(&document, "workbench.colorCustomizations.editor.background").and_then(|v| v.as_str()),
+            Some("#000000")
+        );
+    }
+
+    #[test]
+    fn missing_key_returns_none() {
+        let document = parse(SETTINGS).unwrap();
+        assert!(get(&document, "does.not.exist").is_none());
+    }
+}
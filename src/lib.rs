@@ -1,7 +1,13 @@
 use std::path::PathBuf;
 
-use once_cell::sync::Lazy;
-use regex_lite::Regex;
+mod doctor;
+mod jsonc;
+mod raw_edit;
+mod settings;
+mod theme_file;
+pub use doctor::{check, Report};
+pub use settings::Settings;
+pub use theme_file::ResolvedTheme;
 
 /// Found fonts from the configration
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -18,6 +24,28 @@ impl FoundFonts {
     pub fn terminal(&self) -> &str {
         self.terminal.as_ref()
     }
+
+    /// Parses `editor.fontFamily` into an ordered fallback list, the primary
+    /// font first
+    pub fn editor_fonts(&self) -> Vec<String> {
+        parse_font_fallback_list(&self.editor)
+    }
+
+    /// Parses `terminal.integrated.fontFamily` into an ordered fallback list
+    pub fn terminal_fonts(&self) -> Vec<String> {
+        parse_font_fallback_list(&self.terminal)
+    }
+}
+
+/// Parses a CSS-style comma-separated font fallback chain (e.g.
+/// `"'Fira Code', Consolas, monospace"`), trimming whitespace and stripping
+/// surrounding quotes from each entry while preserving order
+fn parse_font_fallback_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().trim_matches(|c| c == '\'' || c == '"'))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 /// A found theme (extension) from the vscode extension cache
@@ -25,6 +53,9 @@ impl FoundFonts {
 pub struct FoundTheme<'a> {
     id: &'a str,
     variant: &'a str,
+    extension_dir: &'a std::path::Path,
+    theme_path: &'a str,
+    appearance: Option<Appearance>,
 }
 
 impl<'a> FoundTheme<'a> {
@@ -40,11 +71,41 @@ impl<'a> FoundTheme<'a> {
     pub const fn variant(&self) -> &str {
         self.variant
     }
+
+    /// Gets the theme's appearance (dark/light/high-contrast), if known
+    pub const fn appearance(&self) -> Option<Appearance> {
+        self.appearance
+    }
+
+    /// Resolves the theme's full color palette, following its `include` chain
+    ///
+    /// This reads the theme's JSON file (relative to the extension's
+    /// directory) and, if it has an `include`, recursively merges in its
+    /// parent(s) first, so child keys override parent keys.
+    pub fn palette(&self) -> Result<ResolvedTheme> {
+        theme_file::resolve(&self.extension_dir.join(self.theme_path))
+    }
 }
 
-fn make_json_regex(key: &str) -> Regex {
-    let s = key.replace('.', r#"\."#);
-    regex_lite::Regex::new(&format!(r#"(?m)^\s*"{}"\s*:\s*"(?P<name>.*?)",?\s*?$"#, s)).unwrap()
+/// The visual appearance of a theme, derived from its `uiTheme` contribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, ::serde::Serialize, ::serde::Deserialize)]
+pub enum Appearance {
+    Light,
+    Dark,
+    HighContrastDark,
+    HighContrastLight,
+}
+
+impl Appearance {
+    fn from_ui_theme(ui_theme: &str) -> Option<Self> {
+        Some(match ui_theme {
+            "vs" => Self::Light,
+            "vs-dark" => Self::Dark,
+            "hc-black" => Self::HighContrastDark,
+            "hc-light" => Self::HighContrastLight,
+            _ => return None,
+        })
+    }
 }
 
 fn read<F, E>(f: F) -> Result<String>
@@ -55,15 +116,6 @@ where
     Ok(std::fs::read_to_string(f().map_err(Into::into)?)?)
 }
 
-static WORKBENCH_COLOR_THEME: Lazy<regex_lite::Regex> =
-    Lazy::new(|| make_json_regex("workbench.colorTheme"));
-
-static WORKBENCH_EDITOR_FONT: Lazy<regex_lite::Regex> =
-    Lazy::new(|| make_json_regex("editor.fontFamily"));
-
-static WORKBENCH_TERMINAL_FONT: Lazy<regex_lite::Regex> =
-    Lazy::new(|| make_json_regex("terminal.integrated.fontFamily"));
-
 pub fn settings_json_path() -> Result<PathBuf> {
     Ok(directories::BaseDirs::new()
         .ok_or(Error::CannotFindBaseDir)?
@@ -82,6 +134,15 @@ pub fn extension_user_cache_path() -> Result<PathBuf> {
         .join("extensions.json"))
 }
 
+/// Path to the collated theme index cache, invalidated against `extensions.json`'s mtime
+pub fn themes_cache_path() -> Result<PathBuf> {
+    Ok(directories::BaseDirs::new()
+        .ok_or(Error::CannotFindBaseDir)?
+        .config_dir()
+        .join("what_theme")
+        .join("themes.cache"))
+}
+
 /// Reads your current (global) `settings.json` and gets the current active theme
 pub fn get_current_theme() -> Result<String> {
     get_current_theme_from(&read(settings_json_path)?)
@@ -89,7 +150,8 @@ pub fn get_current_theme() -> Result<String> {
 
 /// Get the current active theme from a `&str`
 pub fn get_current_theme_from(data: &str) -> Result<String> {
-    extract(&WORKBENCH_COLOR_THEME, data).ok_or(Error::CannotFindCurrentTheme)
+    let document = jsonc::parse(data)?;
+    extract_str(&document, "workbench.colorTheme").ok_or(Error::CannotFindCurrentTheme)
 }
 
 /// Reads your current (global) `settings.json` and gets the current fonts
@@ -99,13 +161,18 @@ pub fn get_current_fonts() -> Result<FoundFonts> {
 
 /// Get the current fonts from a `&str`
 pub fn get_current_fonts_from(data: &str) -> Result<FoundFonts> {
-    let editor = extract(&WORKBENCH_EDITOR_FONT, data).ok_or(Error::CannotFindEditorFont)?;
-    let terminal = extract(&WORKBENCH_TERMINAL_FONT, data).ok_or(Error::CannotFindTerminalFont)?;
+    let document = jsonc::parse(data)?;
+    let editor =
+        extract_str(&document, "editor.fontFamily").ok_or(Error::CannotFindEditorFont)?;
+    let terminal = extract_str(&document, "terminal.integrated.fontFamily")
+        .ok_or(Error::CannotFindTerminalFont)?;
     Ok(FoundFonts { editor, terminal })
 }
 
-fn extract(re: &Regex, data: &str) -> Option<String> {
-    re.captures(data).map(|cap| cap["name"].to_string())
+fn extract_str(document: &serde_json::Value, key: &str) -> Option<String> {
+    jsonc::get(document, key)
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
 }
 
 /// This reads the vscode extension cache and allows you to find/search for installed themes
@@ -114,9 +181,31 @@ pub struct VsCodeSettings {
 }
 
 impl VsCodeSettings {
-    /// Create a new instance of the `VscodeSettings`
+    /// Create a new instance of the `VscodeSettings`, reusing the on-disk theme
+    /// cache when it is newer than `extensions.json`
     pub fn new() -> Result<Self> {
-        Self::new_from(&read(extension_user_cache_path)?)
+        let extensions_path = extension_user_cache_path()?;
+        if let Some(list) = Self::read_cache(&extensions_path) {
+            return Ok(Self { list });
+        }
+        Self::new_uncached()
+    }
+
+    /// Create a new instance of the `VscodeSettings`, always rescanning every
+    /// installed extension and rewriting the cache
+    pub fn new_uncached() -> Result<Self> {
+        let this = Self::new_from(&read(extension_user_cache_path)?)?;
+        let _ = this.write_cache();
+        Ok(this)
+    }
+
+    /// Deletes the on-disk theme cache, if any
+    pub fn clear_cache() -> Result<()> {
+        match std::fs::remove_file(themes_cache_path()?) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
     }
 
     /// Create a new instance of the `VscodeSettings` from str
@@ -125,13 +214,48 @@ impl VsCodeSettings {
         Ok(Self { list })
     }
 
+    fn read_cache(extensions_path: &std::path::Path) -> Option<Vec<LabeledTheme>> {
+        let cache_path = themes_cache_path().ok()?;
+        let cache_mtime = std::fs::metadata(&cache_path).ok()?.modified().ok()?;
+        let extensions_mtime = std::fs::metadata(extensions_path).ok()?.modified().ok()?;
+        if cache_mtime <= extensions_mtime {
+            return None;
+        }
+        let data = std::fs::read(cache_path).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+
+    fn write_cache(&self) -> Result<()> {
+        let path = themes_cache_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = bincode::serialize(&self.list)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
     /// Filters the cache by a variant name
     pub fn find_theme<'a>(&'a self, current: &'a str) -> Option<FoundTheme<'a>> {
+        self.find_theme_matching(current, None)
+    }
+
+    /// Filters the cache by a variant name, optionally restricted to a
+    /// specific [`Appearance`] (the common case when scripting automatic
+    /// day/night theme switching)
+    pub fn find_theme_matching<'a>(
+        &'a self,
+        current: &'a str,
+        appearance: Option<Appearance>,
+    ) -> Option<FoundTheme<'a>> {
         self.list.iter().find_map(|c| {
-            if &*c.label == current {
+            if &*c.label == current && appearance.is_none_or(|want| c.appearance == Some(want)) {
                 Some(FoundTheme {
                     id: &c.id,
                     variant: &c.label,
+                    extension_dir: &c.extension_dir,
+                    theme_path: &c.theme_path,
+                    appearance: c.appearance,
                 })
             } else {
                 None
@@ -139,6 +263,21 @@ impl VsCodeSettings {
         })
     }
 
+    /// Lists all installed themes matching the given [`Appearance`]
+    pub fn themes_with_appearance(&self, appearance: Appearance) -> Vec<FoundTheme<'_>> {
+        self.list
+            .iter()
+            .filter(|c| c.appearance == Some(appearance))
+            .map(|c| FoundTheme {
+                id: &c.id,
+                variant: &c.label,
+                extension_dir: &c.extension_dir,
+                theme_path: &c.theme_path,
+                appearance: c.appearance,
+            })
+            .collect()
+    }
+
     fn collate_themes(list: Vec<vscode_data::Result>) -> Vec<LabeledTheme> {
         fn undo_the_node_path_thing(s: &str) -> &str {
             if cfg!(target_os = "windows") {
@@ -158,17 +297,29 @@ impl VsCodeSettings {
                             Ok(path) => path,
                             Err(_err) => return,
                         };
+                        let extension_dir = match path.parent() {
+                            Some(dir) => dir.to_path_buf(),
+                            None => return,
+                        };
 
-                        if let Ok(data) = std::fs::read_to_string(&path) {
-                            if let Ok(manifest) =
-                                serde_json::from_str::<vscode_data::Manifest>(&data)
-                            {
-                                for theme in manifest.contributes.themes {
-                                    let _ = tx.send(LabeledTheme {
-                                        label: theme.label,
-                                        id: result.identifier.id.clone(),
-                                    });
-                                }
+                        let manifest = std::fs::read_to_string(&path)
+                            .ok()
+                            .and_then(|data| serde_json::from_str::<vscode_data::Manifest>(&data).ok());
+
+                        if let Some(manifest) = manifest {
+                            for theme in manifest.contributes.themes {
+                                let appearance = theme
+                                    .ui_theme
+                                    .as_deref()
+                                    .and_then(Appearance::from_ui_theme);
+                                let _ = tx.send(LabeledTheme {
+                                    label: theme.label,
+                                    id: result.identifier.id.clone(),
+                                    extension_dir: extension_dir.clone(),
+                                    theme_path: theme.path,
+                                    ui_theme: theme.ui_theme,
+                                    appearance,
+                                });
                             }
                         }
                     }
@@ -209,12 +360,28 @@ pub enum Error {
     /// A serialization problem
     #[error("cannot deserialize user cache file")]
     Json(#[from] serde_json::Error),
+
+    /// The theme cache could not be (de)serialized
+    #[error("cannot (de)serialize the theme cache")]
+    Cache(#[from] bincode::Error),
+
+    /// A theme file referenced via `include` does not exist
+    #[error("missing theme file: {}", .0.display())]
+    MissingThemeFile(PathBuf),
+
+    /// A theme's `include` chain referenced itself
+    #[error("include cycle detected at: {}", .0.display())]
+    IncludeCycle(PathBuf),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]
 pub struct LabeledTheme {
     pub label: String,
     pub id: String,
+    pub extension_dir: PathBuf,
+    pub theme_path: String,
+    pub ui_theme: Option<String>,
+    pub appearance: Option<Appearance>,
 }
 
 mod vscode_data {
@@ -251,6 +418,9 @@ mod vscode_data {
     #[derive(::serde::Deserialize, Debug)]
     pub struct Theme {
         pub label: String,
+        pub path: String,
+        #[serde(rename = "uiTheme", default)]
+        pub ui_theme: Option<String>,
     }
 }
 
@@ -267,3 +437,34 @@ mod vscode_data {
 //     //         .any(|c| c.label == theme)
 //     // }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_known_ui_themes() {
+        assert_eq!(Appearance::from_ui_theme("vs"), Some(Appearance::Light));
+        assert_eq!(Appearance::from_ui_theme("vs-dark"), Some(Appearance::Dark));
+        assert_eq!(
+            Appearance::from_ui_theme("hc-black"),
+            Some(Appearance::HighContrastDark)
+        );
+        assert_eq!(
+            Appearance::from_ui_theme("hc-light"),
+            Some(Appearance::HighContrastLight)
+        );
+        assert_eq!(Appearance::from_ui_theme("something-else"), None);
+    }
+
+    #[test]
+    fn parses_font_fallback_list_preserving_order() {
+        let fonts = parse_font_fallback_list("'Fira Code', \"Consolas\", monospace");
+        assert_eq!(fonts, vec!["Fira Code", "Consolas", "monospace"]);
+    }
+
+    #[test]
+    fn parses_single_unquoted_font() {
+        assert_eq!(parse_font_fallback_list("Consolas"), vec!["Consolas"]);
+    }
+}
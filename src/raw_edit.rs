@@ -0,0 +1,154 @@
+//! A minimal in-place editor for a JSONC document: locates a top-level key's
+//! raw byte span so a write only replaces the bytes that actually changed,
+//! leaving the rest of the file's comments and formatting untouched.
+
+/// Where a top-level key was found (or should be inserted) in the raw text
+pub(crate) enum KeySpan {
+    /// The key already exists; this is the byte range of its value
+    Existing(std::ops::Range<usize>),
+    /// The key is missing; insert a new `"key": value` entry at this offset
+    /// (preceded by a `,` when [`needs_separator`] says so)
+    Missing(usize),
+}
+
+/// Scans `text` for a top-level (depth-1) occurrence of `"key"`, skipping
+/// over comments and string contents so braces/brackets inside them don't
+/// confuse the depth count
+pub(crate) fn find_top_level_key(text: &str, key: &str) -> KeySpan {
+    let bytes = text.as_bytes();
+    let quoted_key = format!("\"{key}\"");
+    let mut i = 0;
+    let mut depth = 0i32;
+    let mut root_open = None;
+    let mut root_close = None;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if b == b'"' {
+            if depth == 1 && text[i..].starts_with(&quoted_key) {
+                let after_key = i + quoted_key.len();
+                let colon_rel = text[after_key..].find(':');
+                let is_bare_key =
+                    colon_rel.is_some_and(|rel| text[after_key..after_key + rel].trim().is_empty());
+                if is_bare_key {
+                    let value_start = skip_ws(text, after_key + colon_rel.unwrap() + 1);
+                    return KeySpan::Existing(value_start..scan_value_end(text, value_start));
+                }
+            }
+            i = skip_string(bytes, i);
+            continue;
+        }
+
+        if b == b'/' && bytes.get(i + 1) == Some(&b'/') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        if b == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+            continue;
+        }
+
+        match b {
+            b'{' => {
+                depth += 1;
+                if depth == 1 {
+                    root_open = Some(i + 1);
+                }
+            }
+            b'}' => {
+                if depth == 1 {
+                    root_close = Some(i);
+                }
+                depth -= 1;
+            }
+            b'[' => depth += 1,
+            b']' => depth -= 1,
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    KeySpan::Missing(root_close.or(root_open).unwrap_or(text.len()))
+}
+
+/// Given the start of a JSON value, returns the index right after it ends
+/// (before any trailing comma/whitespace)
+fn scan_value_end(text: &str, start: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = start;
+    let mut depth = 0i32;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if b == b'"' {
+            let end = skip_string(bytes, i);
+            if depth == 0 {
+                return end;
+            }
+            i = end;
+            continue;
+        }
+
+        if depth == 0 && (b == b',' || b == b'}' || b == b']' || b.is_ascii_whitespace()) {
+            return i;
+        }
+        if depth == 0 && b == b'/' && matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')) {
+            return i;
+        }
+
+        match b {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+/// Returns the index right after the closing quote of the string starting at
+/// `start` (which must point at the opening `"`)
+fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+fn skip_ws(text: &str, mut i: usize) -> usize {
+    let bytes = text.as_bytes();
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Whether a new entry inserted right before `before` needs a leading `,` to
+/// separate it from whatever precedes it (nothing does if it's the first
+/// entry in the object, or the preceding entry already ends in a comma)
+pub(crate) fn needs_separator(text: &str, before: usize) -> bool {
+    let preceding = crate::jsonc::strip(&text[..before]);
+    !matches!(preceding.trim_end().chars().last(), None | Some('{') | Some(','))
+}
@@ -0,0 +1,123 @@
+//! An order-preserving view of vscode's `settings.json`, supporting reads of
+//! arbitrary dotted keys and write-back of changes (such as switching the
+//! active theme), the natural companion to enumerating installed themes.
+
+use std::path::PathBuf;
+
+use crate::{jsonc, raw_edit, settings_json_path, Result};
+
+/// A loaded `settings.json`. Reads go through the parsed document; writes
+/// patch only the changed key's raw bytes, so comments and formatting
+/// elsewhere in the file are left untouched.
+pub struct Settings {
+    path: PathBuf,
+    text: String,
+    document: serde_json::Value,
+}
+
+impl Settings {
+    /// Loads your current (global) `settings.json`
+    pub fn load() -> Result<Self> {
+        Self::load_from(settings_json_path()?)
+    }
+
+    fn load_from(path: PathBuf) -> Result<Self> {
+        let text = std::fs::read_to_string(&path)?;
+        let document = jsonc::parse(&text)?;
+        Ok(Self { path, text, document })
+    }
+
+    /// Gets a value by key, e.g. `workbench.colorCustomizations`
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        jsonc::get(&self.document, key)
+    }
+
+    /// Sets a key's value, writing only that key's bytes back to disk and
+    /// preserving the rest of the file's comments, key order and formatting
+    pub fn set(&mut self, key: &str, value: serde_json::Value) -> Result<()> {
+        let rendered = serde_json::to_string(&value)?;
+
+        match raw_edit::find_top_level_key(&self.text, key) {
+            raw_edit::KeySpan::Existing(span) => self.text.replace_range(span, &rendered),
+            raw_edit::KeySpan::Missing(at) => {
+                let entry = if raw_edit::needs_separator(&self.text, at) {
+                    format!(",\"{key}\":{rendered}")
+                } else {
+                    format!("\"{key}\":{rendered}")
+                };
+                self.text.insert_str(at, &entry);
+            }
+        }
+
+        self.document = jsonc::parse(&self.text)?;
+        std::fs::write(&self.path, &self.text)?;
+        Ok(())
+    }
+
+    /// Sets `workbench.colorTheme` and writes the change back to disk
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        self.set(
+            "workbench.colorTheme",
+            serde_json::Value::String(name.to_string()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("what_theme_settings_{}_{name}.json", std::process::id()))
+    }
+
+    #[test]
+    fn set_theme_writes_a_flat_key_and_preserves_comments() {
+        let path = test_path("set_theme");
+        std::fs::write(
+            &path,
+            "{\n  // keep me\n  \"workbench.colorTheme\": \"Old Theme\",\n  \"editor.fontSize\": 14,\n}\n",
+        )
+        .unwrap();
+
+        let mut settings = Settings::load_from(path.clone()).unwrap();
+        settings.set_theme("New Theme").unwrap();
+
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(on_disk.contains("// keep me"));
+        assert!(on_disk.contains("\"workbench.colorTheme\": \"New Theme\""));
+        assert!(on_disk.contains("\"editor.fontSize\": 14"));
+
+        assert_eq!(
+            settings.get("workbench.colorTheme").and_then(|v| v.as_str()),
+            Some("New Theme")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn set_inserts_a_missing_key() {
+        let path = test_path("insert");
+        std::fs::write(&path, "{\n  \"editor.fontSize\": 14\n}\n").unwrap();
+
+        let mut settings = Settings::load_from(path.clone()).unwrap();
+        settings
+            .set(
+                "workbench.colorTheme",
+                serde_json::Value::String("New Theme".to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            settings.get("workbench.colorTheme").and_then(|v| v.as_str()),
+            Some("New Theme")
+        );
+        assert_eq!(
+            settings.get("editor.fontSize").and_then(|v| v.as_i64()),
+            Some(14)
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
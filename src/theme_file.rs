@@ -0,0 +1,112 @@
+//! Resolves a vscode color theme file into a single flat color map by
+//! following its `include` chain, the way Zed resolves `extends` references.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::{jsonc, Error, Result};
+
+/// A theme with its `include` chain fully resolved: parent `colors` and
+/// `tokenColors` are merged in first, then overridden by each child
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedTheme {
+    pub colors: HashMap<String, String>,
+    pub token_colors: Vec<serde_json::Value>,
+}
+
+#[derive(::serde::Deserialize, Default, Debug)]
+#[serde(default)]
+struct ThemeFile {
+    include: Option<String>,
+    colors: HashMap<String, String>,
+    #[serde(rename = "tokenColors")]
+    token_colors: Vec<serde_json::Value>,
+}
+
+pub(crate) fn resolve(path: &Path) -> Result<ResolvedTheme> {
+    let mut visited = HashSet::new();
+    let mut resolved = ResolvedTheme::default();
+    resolve_into(path, &mut visited, &mut resolved)?;
+    Ok(resolved)
+}
+
+fn resolve_into(path: &Path, visited: &mut HashSet<PathBuf>, resolved: &mut ResolvedTheme) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .map_err(|_err| Error::MissingThemeFile(path.to_path_buf()))?;
+    if !visited.insert(path.clone()) {
+        return Err(Error::IncludeCycle(path));
+    }
+
+    let data = std::fs::read_to_string(&path).map_err(|_err| Error::MissingThemeFile(path.clone()))?;
+    let file: ThemeFile = serde_json::from_str(&jsonc::strip(&data))?;
+
+    if let Some(include) = &file.include {
+        let parent_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        resolve_into(&parent_dir.join(include), visited, resolved)?;
+    }
+
+    resolved.colors.extend(file.colors);
+    resolved.token_colors.extend(file.token_colors);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("what_theme_theme_file_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn merges_an_include_chain_with_child_overriding_parent() {
+        let dir = test_dir("merge");
+        std::fs::write(
+            dir.join("base.json"),
+            r##"{ "colors": { "editor.background": "#111111", "editor.foreground": "#eeeeee" } }"##,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("child.json"),
+            r##"{
+                "include": "./base.json",
+                "colors": { "editor.background": "#222222" },
+                "tokenColors": [{ "scope": "comment", "settings": { "foreground": "#999999" } }],
+            }"##,
+        )
+        .unwrap();
+
+        let resolved = resolve(&dir.join("child.json")).unwrap();
+        assert_eq!(resolved.colors["editor.background"], "#222222");
+        assert_eq!(resolved.colors["editor.foreground"], "#eeeeee");
+        assert_eq!(resolved.token_colors.len(), 1);
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = test_dir("cycle");
+        std::fs::write(dir.join("a.json"), r#"{ "include": "./b.json" }"#).unwrap();
+        std::fs::write(dir.join("b.json"), r#"{ "include": "./a.json" }"#).unwrap();
+
+        assert!(matches!(
+            resolve(&dir.join("a.json")),
+            Err(Error::IncludeCycle(_))
+        ));
+    }
+
+    #[test]
+    fn missing_include_is_an_error() {
+        let dir = test_dir("missing");
+        std::fs::write(dir.join("child.json"), r#"{ "include": "./nope.json" }"#).unwrap();
+
+        assert!(matches!(
+            resolve(&dir.join("child.json")),
+            Err(Error::MissingThemeFile(_))
+        ));
+    }
+}